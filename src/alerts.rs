@@ -0,0 +1,261 @@
+use crate::app::{OwnedParsedLine, TimeSeries};
+use std::time::Duration;
+
+/// How an alert rule's field value is compared against its threshold.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum AlertComparison {
+    GreaterThan,
+    LessThan,
+    Equal,
+}
+
+impl AlertComparison {
+    fn holds(self, value: f64, threshold: f64) -> bool {
+        match self {
+            AlertComparison::GreaterThan => value > threshold,
+            AlertComparison::LessThan => value < threshold,
+            AlertComparison::Equal => value == threshold,
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            AlertComparison::GreaterThan => ">",
+            AlertComparison::LessThan => "<",
+            AlertComparison::Equal => "==",
+        }
+    }
+}
+
+/// A threshold rule: fires when `field` on the newest datum matching `measurement`
+/// and `tag_filters` satisfies `comparison` against `threshold` continuously for at
+/// least `debounce`, so a flapping value doesn't spam a toast per frame.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct AlertRule {
+    pub name: String,
+    pub measurement: String,
+    pub tag_filters: Vec<(String, String)>,
+    pub field: String,
+    pub comparison: AlertComparison,
+    pub threshold: f64,
+    pub debounce: Duration,
+
+    /// Whether the rule is currently firing. Runtime state, not persisted.
+    #[serde(skip)]
+    firing: bool,
+
+    /// When the condition most recently started holding without interruption, used
+    /// to apply `debounce` before raising the rising edge. Cleared the moment the
+    /// condition stops holding.
+    #[serde(skip)]
+    condition_since: Option<Duration>,
+}
+
+impl Default for AlertRule {
+    fn default() -> Self {
+        // Mirrors GraphQuery's default: the monitor's original total-CPU-usage series.
+        AlertRule {
+            name: "New alert".to_string(),
+            measurement: "cpu".to_string(),
+            tag_filters: vec![("cpu".to_string(), "cpu-total".to_string())],
+            field: "usage_idle".to_string(),
+            comparison: AlertComparison::LessThan,
+            threshold: 10.0,
+            debounce: Duration::from_secs(5),
+            firing: false,
+            condition_since: None,
+        }
+    }
+}
+
+impl AlertRule {
+    pub(crate) fn is_firing(&self) -> bool {
+        self.firing
+    }
+
+    fn matches_tag_filters(&self, line: &OwnedParsedLine) -> bool {
+        self.tag_filters
+            .iter()
+            .all(|(key, value)| line.tags.iter().any(|(k, v)| k == key && v == value))
+    }
+
+    /// The newest datum matching this rule's measurement and tag filters, if any.
+    fn newest_match<'a>(
+        &self,
+        time_series: &'a TimeSeries<OwnedParsedLine>,
+    ) -> Option<&'a OwnedParsedLine> {
+        time_series
+            .data
+            .iter()
+            .rev()
+            .map(|datum| &datum.data)
+            .find(|line| line.measurement == self.measurement && self.matches_tag_filters(line))
+    }
+
+    /// Evaluates the rule against `time_series` and returns a toast message exactly on
+    /// a rising edge (the frame the rule starts firing after holding for `debounce`).
+    fn evaluate(&mut self, time_series: &TimeSeries<OwnedParsedLine>, now: Duration) -> Option<String> {
+        let Some(line) = self.newest_match(time_series) else {
+            self.condition_since = None;
+            self.firing = false;
+            return None;
+        };
+
+        let value = line.get_field_as_f64(&self.field, 0.0);
+        if !self.comparison.holds(value, self.threshold) {
+            self.condition_since = None;
+            self.firing = false;
+            return None;
+        }
+
+        let holding_since = *self.condition_since.get_or_insert(now);
+        if !self.firing && now.saturating_sub(holding_since) >= self.debounce {
+            self.firing = true;
+            return Some(format!(
+                "{}: {} {} {} (currently {value:.2})",
+                self.name,
+                self.field,
+                self.comparison.symbol(),
+                self.threshold
+            ));
+        }
+
+        None
+    }
+}
+
+/// How long a toast stays on screen after its rule's rising edge.
+const TOAST_LIFETIME: Duration = Duration::from_secs(6);
+
+/// A transient on-screen notification raised when an alert rule's rising edge fires.
+#[derive(Debug, Clone)]
+pub(crate) struct Toast {
+    message: String,
+    shown_at: Duration,
+}
+
+/// Evaluates every rule against `time_series`, appending a [`Toast`] for each rule
+/// that has a rising edge this frame, and dropping toasts older than
+/// [`TOAST_LIFETIME`]. Called once per `update`, after metrics have been received.
+pub(crate) fn evaluate_rules(
+    rules: &mut [AlertRule],
+    time_series: &TimeSeries<OwnedParsedLine>,
+    toasts: &mut Vec<Toast>,
+    now: Duration,
+) {
+    toasts.retain(|toast| now.saturating_sub(toast.shown_at) < TOAST_LIFETIME);
+
+    for rule in rules.iter_mut() {
+        if let Some(message) = rule.evaluate(time_series, now) {
+            toasts.push(Toast {
+                message,
+                shown_at: now,
+            });
+        }
+    }
+}
+
+/// Draws active toasts stacked below the top-right corner of the window.
+pub(crate) fn show_toasts(ctx: &egui::Context, toasts: &[Toast]) {
+    for (i, toast) in toasts.iter().enumerate() {
+        egui::Area::new(egui::Id::new("alert_toast").with(i))
+            .anchor(
+                egui::Align2::RIGHT_TOP,
+                egui::vec2(-8.0, 8.0 + i as f32 * 40.0),
+            )
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.colored_label(egui::Color32::from_rgb(220, 50, 50), &toast.message);
+                });
+            });
+    }
+}
+
+/// Lets the user add, edit, and remove alert rules, and shows each rule's current
+/// firing status.
+pub(crate) fn show_rules_panel(ctx: &egui::Context, rules: &mut Vec<AlertRule>) {
+    egui::Window::new("Alerts").show(ctx, |ui| {
+        let mut remove_rule = None;
+        for (i, rule) in rules.iter_mut().enumerate() {
+            let status = if rule.is_firing() { " [FIRING]" } else { "" };
+            egui::CollapsingHeader::new(format!("{}{status}", rule.name))
+                .id_salt(("alert_rule", i))
+                .show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Name:");
+                        ui.text_edit_singleline(&mut rule.name);
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Measurement:");
+                        ui.text_edit_singleline(&mut rule.measurement);
+                    });
+
+                    ui.label("Tag filters:");
+                    let mut remove_filter = None;
+                    for (fi, (key, value)) in rule.tag_filters.iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.text_edit_singleline(key);
+                            ui.text_edit_singleline(value);
+                            if ui.small_button("x").clicked() {
+                                remove_filter = Some(fi);
+                            }
+                        });
+                    }
+                    if let Some(fi) = remove_filter {
+                        rule.tag_filters.remove(fi);
+                    }
+                    if ui.button("Add tag filter").clicked() {
+                        rule.tag_filters.push((String::new(), String::new()));
+                    }
+
+                    ui.horizontal(|ui| {
+                        ui.label("Field:");
+                        ui.text_edit_singleline(&mut rule.field);
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Comparison:");
+                        egui::ComboBox::from_id_salt(("alert_comparison", i))
+                            .selected_text(rule.comparison.symbol())
+                            .show_ui(ui, |ui| {
+                                for comparison in [
+                                    AlertComparison::GreaterThan,
+                                    AlertComparison::LessThan,
+                                    AlertComparison::Equal,
+                                ] {
+                                    ui.selectable_value(
+                                        &mut rule.comparison,
+                                        comparison,
+                                        comparison.symbol(),
+                                    );
+                                }
+                            });
+                        ui.add(egui::DragValue::new(&mut rule.threshold).prefix("threshold: "));
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Debounce:");
+                        let mut debounce_secs = rule.debounce.as_secs_f64();
+                        if ui
+                            .add(egui::DragValue::new(&mut debounce_secs).suffix("s"))
+                            .changed()
+                        {
+                            rule.debounce = Duration::from_secs_f64(debounce_secs.max(0.0));
+                        }
+                    });
+
+                    if ui.button("Remove rule").clicked() {
+                        remove_rule = Some(i);
+                    }
+                });
+        }
+        if let Some(i) = remove_rule {
+            rules.remove(i);
+        }
+
+        ui.separator();
+        if ui.button("Add alert rule").clicked() {
+            rules.push(AlertRule::default());
+        }
+    });
+}
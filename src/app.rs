@@ -1,19 +1,97 @@
-use egui::{Color32, Context};
+use crate::alerts::{AlertRule, Toast};
+use crate::card::{Card, CardTabViewer, GraphCard};
+use crate::store::MetricStoreHandle;
+use egui::Context;
+use egui_dock::DockState;
 use egui_extras::{Column, TableBuilder};
-use egui_plot::{Line, Plot, PlotBounds, PlotPoints};
 use influxdb_line_protocol::{parse_lines, FieldValue, ParsedLine};
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::io::{BufRead, BufReader};
-use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::net::{SocketAddr, TcpListener, TcpStream, UdpSocket};
+use std::path::PathBuf;
 use std::sync::mpsc;
 use std::sync::mpsc::{Receiver, Sender};
 use std::thread;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-const WINDOW_LENGTH: Duration = Duration::from_secs(60);
+pub(crate) const WINDOW_LENGTH: Duration = Duration::from_secs(60);
+
+/// How often a non-`Live` history query is re-issued, so panning to "Last 24 hours"
+/// doesn't show an ever-staler snapshot taken only at selection time.
+const HISTORY_REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Time ranges the user can pick in the top panel's history control. `Live` plots
+/// straight from the in-memory window; the others query the on-disk store.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TimeRange {
+    Live,
+    LastMinutes(u64),
+    LastHours(u64),
+}
+
+impl TimeRange {
+    const OPTIONS: [TimeRange; 5] = [
+        TimeRange::Live,
+        TimeRange::LastMinutes(5),
+        TimeRange::LastMinutes(15),
+        TimeRange::LastHours(1),
+        TimeRange::LastHours(24),
+    ];
+
+    fn window(self) -> Option<Duration> {
+        match self {
+            TimeRange::Live => None,
+            TimeRange::LastMinutes(minutes) => Some(Duration::from_secs(minutes * 60)),
+            TimeRange::LastHours(hours) => Some(Duration::from_secs(hours * 3600)),
+        }
+    }
+
+    fn label(self) -> String {
+        match self {
+            TimeRange::Live => format!("Live (last {}s)", WINDOW_LENGTH.as_secs()),
+            TimeRange::LastMinutes(minutes) => format!("Last {minutes} minutes"),
+            TimeRange::LastHours(hours) => format!("Last {hours} hours"),
+        }
+    }
+}
+
+impl Default for TimeRange {
+    fn default() -> Self {
+        TimeRange::Live
+    }
+}
+
+/// Maximum size of a single UDP datagram we'll accept from a line protocol writer.
+const UDP_RECV_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Which network transport(s) the monitor listens for InfluxDB line protocol on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transport {
+    Tcp,
+    Udp,
+    Both,
+}
+
+/// Listener configuration: which transport(s) to use and which port to bind each to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListenConfig {
+    pub transport: Transport,
+    pub tcp_port: u16,
+    pub udp_port: u16,
+}
+
+impl Default for ListenConfig {
+    fn default() -> Self {
+        ListenConfig {
+            transport: Transport::Both,
+            tcp_port: 8094,
+            udp_port: 8094,
+        }
+    }
+}
 
 #[derive(Debug, Clone, PartialEq)]
-enum MeasurementValue {
+pub(crate) enum MeasurementValue {
     I64(i64),
     U64(u64),
     F64(f64),
@@ -53,14 +131,19 @@ impl<'a> From<&FieldValue<'a>> for MeasurementValue {
 
 /// Stores an owned version of `ParsedLine`
 #[derive(Debug, Clone)]
-struct OwnedParsedLine {
-    measurement: String,
-    tags: Vec<(String, String)>,
-    fields: Vec<(String, MeasurementValue)>,
-    unix_timestamp: Duration,
+pub(crate) struct OwnedParsedLine {
+    pub(crate) measurement: String,
+    pub(crate) tags: Vec<(String, String)>,
+    pub(crate) fields: Vec<(String, MeasurementValue)>,
+    pub(crate) unix_timestamp: Duration,
+    /// The connection this line arrived on, so the UI can track and filter by source.
+    pub(crate) source: SocketAddr,
 }
-impl<'a> From<&ParsedLine<'a>> for OwnedParsedLine {
-    fn from(parsed: &ParsedLine<'a>) -> Self {
+
+impl OwnedParsedLine {
+    /// Builds an owned line from a borrowed `ParsedLine`, tagging it with the peer
+    /// address it was read from.
+    fn from_parsed(parsed: &ParsedLine<'_>, source: SocketAddr) -> Self {
         let unix_timestamp = parsed
             .timestamp
             .and_then(|ns| ns.try_into().ok())
@@ -85,6 +168,7 @@ impl<'a> From<&ParsedLine<'a>> for OwnedParsedLine {
                 .map(|(k, v)| (k.to_string(), v.into()))
                 .collect(),
             unix_timestamp,
+            source,
         }
     }
 }
@@ -103,15 +187,15 @@ impl OwnedParsedLine {
 }
 
 #[derive(Debug)]
-struct TimeSeriesDatum<T> {
-    unix_timestamp: Duration,
-    data: T,
+pub(crate) struct TimeSeriesDatum<T> {
+    pub(crate) unix_timestamp: Duration,
+    pub(crate) data: T,
 }
 
 //
 #[derive(Debug)]
-struct TimeSeries<T> {
-    data: VecDeque<TimeSeriesDatum<T>>,
+pub(crate) struct TimeSeries<T> {
+    pub(crate) data: VecDeque<TimeSeriesDatum<T>>,
 }
 
 impl<T> TimeSeries<T> {
@@ -121,7 +205,7 @@ impl<T> TimeSeries<T> {
         }
     }
 
-    fn push(&mut self, unix_timestamp: Duration, data: T) {
+    pub(crate) fn push(&mut self, unix_timestamp: Duration, data: T) {
         self.data.push_back(TimeSeriesDatum {
             unix_timestamp,
             data,
@@ -139,6 +223,25 @@ impl<T> TimeSeries<T> {
     }
 }
 
+/// What we know about a peer that has sent us line protocol data.
+#[derive(Debug, Clone)]
+pub(crate) struct SourceInfo {
+    pub(crate) last_seen: Duration,
+    pub(crate) line_count: u64,
+    /// Whether data from this source is included in plots and the debug window.
+    pub(crate) enabled: bool,
+}
+
+impl Default for SourceInfo {
+    fn default() -> Self {
+        SourceInfo {
+            last_seen: Duration::ZERO,
+            line_count: 0,
+            enabled: true,
+        }
+    }
+}
+
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)] // if we add new fields, give them default values when deserializing old state
@@ -147,18 +250,75 @@ pub struct PatinaSystemMonitor {
     metrics_rx: Receiver<(SocketAddr, Vec<OwnedParsedLine>)>,
 
     #[serde(skip)]
-    listen_thread_tx: Sender<()>,
+    listen_thread_tx: Vec<Sender<()>>,
 
     #[serde(skip)]
     time_series: TimeSeries<OwnedParsedLine>,
+
+    /// The dock's full split/tab topology, snapshotted from `dock_state` right before
+    /// saving so layout, not just the set of open cards, survives a restart. Kept over
+    /// `GraphCard` rather than `Box<dyn Card>` because only a concrete type can derive
+    /// `Serialize`/`Deserialize`.
+    dock_layout: DockState<GraphCard>,
+
+    /// The live dock driving the UI, rebuilt from `dock_layout` on load and whenever
+    /// `dock_layout` is refreshed from it before a save.
+    #[serde(skip)]
+    dock_state: DockState<Box<dyn Card>>,
+
+    /// Handle to the background writer/reader thread for the on-disk metric store.
+    /// `None` until `new_with_config` opens it, and stays `None` if opening fails.
+    #[serde(skip)]
+    metric_store: Option<MetricStoreHandle>,
+
+    #[serde(skip)]
+    time_range: TimeRange,
+
+    /// Pending result of a history query issued for `time_range`, polled (without
+    /// blocking) each `update` until the store's worker thread replies.
+    #[serde(skip)]
+    history_rx: Option<Receiver<Vec<crate::store::MetricRow>>>,
+
+    /// Most recent history query result, shown in place of `time_series` whenever
+    /// `time_range` is not `Live`.
+    #[serde(skip)]
+    history_time_series: TimeSeries<OwnedParsedLine>,
+
+    /// When the current `time_range`'s history was last (re)queried, so `update` can
+    /// refresh it periodically instead of leaving it a one-shot snapshot that goes
+    /// stale as new data lands in the store.
+    #[serde(skip)]
+    history_queried_at: Duration,
+
+    /// Every peer we've ever seen data from, keyed by its `SocketAddr`. Drives the
+    /// sources panel and the enabled/disabled filter applied before plotting.
+    #[serde(skip)]
+    sources: BTreeMap<SocketAddr, SourceInfo>,
+
+    /// Threshold alert rules, editable through the rules panel.
+    alert_rules: Vec<AlertRule>,
+
+    /// Toasts currently on screen, raised on an alert rule's rising edge.
+    #[serde(skip)]
+    toasts: Vec<Toast>,
 }
 
 impl Default for PatinaSystemMonitor {
     fn default() -> Self {
         Self {
             metrics_rx: mpsc::channel().1,
-            listen_thread_tx: mpsc::channel().0,
+            listen_thread_tx: Vec::new(),
             time_series: TimeSeries::new(),
+            dock_layout: Self::default_dock_layout(),
+            dock_state: Self::default_dock_state(),
+            metric_store: None,
+            time_range: TimeRange::default(),
+            history_rx: None,
+            history_time_series: TimeSeries::new(),
+            history_queried_at: Duration::ZERO,
+            sources: BTreeMap::new(),
+            alert_rules: Vec::new(),
+            toasts: Vec::new(),
         }
     }
 }
@@ -166,46 +326,93 @@ impl Default for PatinaSystemMonitor {
 impl PatinaSystemMonitor {
     /// Called once before the first frame.
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        Self::new_with_config(cc, ListenConfig::default())
+    }
+
+    /// Like [`Self::new`], but lets the caller choose which transport(s) to listen on
+    /// and which port(s) to bind them to.
+    pub fn new_with_config(cc: &eframe::CreationContext<'_>, config: ListenConfig) -> Self {
         // This is also where you can customize the look and feel of egui using
         // `cc.egui_ctx.set_visuals` and `cc.egui_ctx.set_fonts`.
 
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
-        // if let Some(storage) = cc.storage {
-        //     return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
-        // }
+        let mut monitor: PatinaSystemMonitor = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, eframe::APP_KEY))
+            .unwrap_or_default();
+        monitor.rebuild_dock_state();
 
-        let listener = TcpListener::bind("127.0.0.1:8094")
-            .expect("Failed to bind TCP listener, unhandled error.");
+        let (metrics_tx, metrics_rx) = mpsc::channel::<(SocketAddr, Vec<OwnedParsedLine>)>();
+        let mut listen_thread_tx = Vec::new();
 
-        println!("Listening on port 8094...");
+        if matches!(config.transport, Transport::Tcp | Transport::Both) {
+            listen_thread_tx.push(spawn_tcp_listener(
+                config.tcp_port,
+                metrics_tx.clone(),
+                cc.egui_ctx.clone(),
+            ));
+        }
 
-        let (metrics_tx, metrics_rx) = mpsc::channel::<(SocketAddr, Vec<OwnedParsedLine>)>();
-        let (listen_thread_tx, listen_thread_rx) = mpsc::channel::<()>();
-
-        let ctx = cc.egui_ctx.clone();
-        thread::spawn(move || {
-            while listen_thread_rx.try_recv() != Err(mpsc::TryRecvError::Disconnected) {
-                for stream in listener.incoming() {
-                    match stream {
-                        Ok(stream) => {
-                            println!("New connection: {}", stream.peer_addr().unwrap());
-                            let tx_clone = metrics_tx.clone();
-                            let ctx_clone = ctx.clone();
-                            thread::spawn(|| handle_client(stream, tx_clone, ctx_clone));
-                        }
-                        Err(e) => eprintln!("Connection failed: {}", e),
-                    }
-                }
+        if matches!(config.transport, Transport::Udp | Transport::Both) {
+            listen_thread_tx.push(spawn_udp_listener(
+                config.udp_port,
+                metrics_tx.clone(),
+                cc.egui_ctx.clone(),
+            ));
+        }
+
+        let store_path = eframe::storage_dir("PatinaSystemMonitor")
+            .map(|dir| dir.join("metrics.sqlite3"))
+            .unwrap_or_else(|| PathBuf::from("metrics.sqlite3"));
+        monitor.metric_store = match MetricStoreHandle::open(&store_path) {
+            Ok(store) => Some(store),
+            Err(e) => {
+                eprintln!("Failed to open metric store at {:?}: {}", store_path, e);
+                None
             }
-            println!("Closing listening port.");
-        });
+        };
 
-        PatinaSystemMonitor {
-            metrics_rx,
-            listen_thread_tx,
-            ..Default::default()
-        }
+        monitor.metrics_rx = metrics_rx;
+        monitor.listen_thread_tx = listen_thread_tx;
+        monitor
+    }
+
+    fn default_dock_state() -> DockState<Box<dyn Card>> {
+        let initial_tabs: Vec<Box<dyn Card>> = vec![Box::new(GraphCard::default())];
+        DockState::new(initial_tabs)
+    }
+
+    fn default_dock_layout() -> DockState<GraphCard> {
+        DockState::new(vec![GraphCard::default()])
+    }
+
+    /// Boxes every `GraphCard` in `layout` as a `dyn Card` tab, preserving `layout`'s
+    /// full split/surface topology.
+    fn dock_state_from_layout(layout: &DockState<GraphCard>) -> DockState<Box<dyn Card>> {
+        layout
+            .clone()
+            .map_tabs(|card| Box::new(card.clone()) as Box<dyn Card>)
+    }
+
+    /// Replaces `dock_state` with a fresh dock built from the persisted `dock_layout`,
+    /// e.g. right after loading on startup.
+    fn rebuild_dock_state(&mut self) {
+        self.dock_state = Self::dock_state_from_layout(&self.dock_layout);
+    }
+
+    /// Snapshots `dock_state`'s current topology into `dock_layout` so the whole
+    /// arrangement, not just the set of open cards, round-trips through the next
+    /// `save`.
+    fn snapshot_dock_layout(&mut self) {
+        let dock_state = std::mem::replace(&mut self.dock_state, DockState::new(Vec::new()));
+        self.dock_layout = dock_state.map_tabs(|card| {
+            card.as_any()
+                .downcast_ref::<GraphCard>()
+                .cloned()
+                .unwrap_or_default()
+        });
+        self.rebuild_dock_state();
     }
 
     fn trim_time_series(&mut self) {
@@ -217,25 +424,104 @@ impl PatinaSystemMonitor {
     }
 
     fn recv_metrics(&mut self) {
-        for x in self.metrics_rx.try_iter() {
-            for line in x.1 {
+        for (socket_addr, lines) in self.metrics_rx.try_iter() {
+            if let Some(store) = &self.metric_store {
+                store.write(lines.clone());
+            }
+
+            let source_info = self.sources.entry(socket_addr).or_default();
+            source_info.line_count += lines.len() as u64;
+            if let Some(last_line) = lines.last() {
+                source_info.last_seen = last_line.unix_timestamp;
+            }
+
+            for line in lines {
                 self.time_series.push(line.unix_timestamp, line);
             }
         }
     }
 
-    fn show_debug_metrics(&self, ctx: &egui::Context) {
+    /// Whether `source` should be included in plots and the debug window. Sources we
+    /// haven't seen yet (e.g. historical data loaded before this session) are shown.
+    fn is_source_enabled(&self, source: SocketAddr) -> bool {
+        self.sources
+            .get(&source)
+            .map_or(true, |info| info.enabled)
+    }
+
+    /// Clones the data points in `time_series` whose source is currently enabled.
+    fn filter_by_enabled_sources(
+        &self,
+        time_series: &TimeSeries<OwnedParsedLine>,
+    ) -> TimeSeries<OwnedParsedLine> {
+        let mut filtered = TimeSeries::new();
+        for datum in time_series
+            .data
+            .iter()
+            .filter(|d| self.is_source_enabled(d.data.source))
+        {
+            filtered.push(datum.unix_timestamp, datum.data.clone());
+        }
+        filtered
+    }
+
+    /// Issues (or clears) a history query for the current `time_range`. Called whenever
+    /// the user changes the selection in the top panel, and periodically thereafter by
+    /// `refresh_stale_history` so the view doesn't go stale.
+    fn request_history(&mut self) {
+        let now_unix = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        self.history_queried_at = now_unix;
+
+        let Some(window) = self.time_range.window() else {
+            self.history_rx = None;
+            self.history_time_series = TimeSeries::new();
+            return;
+        };
+
+        if let Some(store) = &self.metric_store {
+            let range_start = now_unix.saturating_sub(window);
+            self.history_rx = Some(store.query(range_start, now_unix));
+        }
+    }
+
+    /// Re-issues the history query for the current `time_range` once
+    /// `HISTORY_REFRESH_INTERVAL` has passed, as long as a query isn't already
+    /// in flight. A no-op while `time_range` is `Live`.
+    fn refresh_stale_history(&mut self) {
+        if self.time_range == TimeRange::Live || self.history_rx.is_some() {
+            return;
+        }
+
+        let now_unix = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        if now_unix.saturating_sub(self.history_queried_at) >= HISTORY_REFRESH_INTERVAL {
+            self.request_history();
+        }
+    }
+
+    /// Polls for a history query result without blocking; `update` calls this every
+    /// frame so a slow query never stalls the UI thread.
+    fn poll_history(&mut self) {
+        let Some(rx) = &self.history_rx else {
+            return;
+        };
+
+        if let Ok(rows) = rx.try_recv() {
+            self.history_time_series = crate::store::rows_to_time_series(rows);
+            self.history_rx = None;
+        }
+    }
+
+    fn show_debug_metrics(&self, ctx: &egui::Context, time_series: &TimeSeries<OwnedParsedLine>) {
         let window = egui::Window::new("Metrics");
         window.show(ctx, |ui| {
-            ui.label(format!("Total metrics: {}", self.time_series.data.len()));
+            ui.label(format!("Total metrics: {}", time_series.data.len()));
             ui.label("All CPU Total Metrics");
             TableBuilder::new(ui)
                 .striped(true)
                 .resizable(true)
                 .column(Column::remainder())
                 .body(|mut body| {
-                    for data in self
-                        .time_series
+                    for data in time_series
                         .data
                         .iter()
                         .filter(|d| d.data.measurement == "cpu")
@@ -256,36 +542,55 @@ impl PatinaSystemMonitor {
         });
     }
 
-    fn simple_plot(&self, ui: &mut egui::Ui) {
-        let now_unix = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
-        let plot = Plot::new("cpu_graph").height(300.0);
-
-        plot.show(ui, |plot_ui| {
-            plot_ui.set_plot_bounds(PlotBounds::from_min_max(
-                [-WINDOW_LENGTH.as_secs_f64(), 0.0],
-                [-1.1, 100.0],
-            ));
+    /// Shows every source we've seen data from, with a toggle to include/exclude it
+    /// from plots and the debug window.
+    fn show_sources_panel(&mut self, ctx: &egui::Context) {
+        let now_unix = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
 
-            let plot_points = self
-                .time_series
-                .data
-                .iter()
-                .filter(|d| d.data.measurement == "cpu")
-                .filter(|d| {
-                    d.data
-                        .tags
-                        .iter()
-                        .any(|(k, v)| k == "cpu" && v == "cpu-total")
-                })
-                .map(|d| {
-                    [
-                        d.data.offset_timestamp_secs_f64(now_unix),
-                        100.0 - d.data.get_field_as_f64("usage_idle", 0.0),
-                    ]
+        let window = egui::Window::new("Sources");
+        window.show(ctx, |ui| {
+            TableBuilder::new(ui)
+                .striped(true)
+                .resizable(true)
+                .column(Column::auto())
+                .column(Column::auto())
+                .column(Column::auto())
+                .column(Column::remainder())
+                .header(18.0, |mut header| {
+                    header.col(|ui| {
+                        ui.strong("Source");
+                    });
+                    header.col(|ui| {
+                        ui.strong("Last seen");
+                    });
+                    header.col(|ui| {
+                        ui.strong("Lines");
+                    });
+                    header.col(|ui| {
+                        ui.strong("Enabled");
+                    });
                 })
-                .collect();
-
-            plot_ui.line(Line::new(PlotPoints::new(plot_points)).color(Color32::RED));
+                .body(|mut body| {
+                    for (addr, info) in self.sources.iter_mut() {
+                        body.row(18.0, |mut row| {
+                            row.col(|ui| {
+                                ui.label(addr.to_string());
+                            });
+                            row.col(|ui| {
+                                let age_secs = now_unix.saturating_sub(info.last_seen).as_secs();
+                                ui.label(format!("{age_secs}s ago"));
+                            });
+                            row.col(|ui| {
+                                ui.label(info.line_count.to_string());
+                            });
+                            row.col(|ui| {
+                                ui.checkbox(&mut info.enabled, "");
+                            });
+                        });
+                    }
+                });
         });
     }
 }
@@ -295,8 +600,28 @@ impl eframe::App for PatinaSystemMonitor {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         self.trim_time_series();
         self.recv_metrics();
-
-        self.show_debug_metrics(ctx);
+        self.poll_history();
+        self.refresh_stale_history();
+
+        let active_time_series = match self.time_range {
+            TimeRange::Live => &self.time_series,
+            _ => &self.history_time_series,
+        };
+        let plot_window = match self.time_range {
+            TimeRange::Live => Some(WINDOW_LENGTH),
+            _ => None,
+        };
+        let visible_time_series = self.filter_by_enabled_sources(active_time_series);
+
+        self.show_debug_metrics(ctx, &visible_time_series);
+        self.show_sources_panel(ctx);
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        crate::alerts::evaluate_rules(&mut self.alert_rules, &self.time_series, &mut self.toasts, now);
+        crate::alerts::show_rules_panel(ctx, &mut self.alert_rules);
+        crate::alerts::show_toasts(ctx, &self.toasts);
 
         // Put your widgets into a `SidePanel`, `TopBottomPanel`, `CentralPanel`, `Window` or `Area`.
         // For inspiration and more examples, go to https://emilk.github.io/egui
@@ -315,15 +640,38 @@ impl eframe::App for PatinaSystemMonitor {
                     ui.add_space(16.0);
                 }
 
+                if ui.button("Add graph").clicked() {
+                    self.dock_state
+                        .push_to_focused_leaf(Box::new(GraphCard::default()));
+                }
+
+                ui.add_space(16.0);
+
+                ui.label("History:");
+                let previous_time_range = self.time_range;
+                egui::ComboBox::from_id_salt("time_range")
+                    .selected_text(self.time_range.label())
+                    .show_ui(ui, |ui| {
+                        for option in TimeRange::OPTIONS {
+                            ui.selectable_value(&mut self.time_range, option, option.label());
+                        }
+                    });
+                if self.time_range != previous_time_range {
+                    self.request_history();
+                }
+
                 egui::widgets::global_theme_preference_buttons(ui);
             });
         });
 
         egui::CentralPanel::default().show(ctx, |ui| {
             // The central panel the region left after adding TopPanel's and SidePanel's
-            ui.heading("CPU");
-
-            self.simple_plot(ui);
+            let mut tab_viewer = CardTabViewer {
+                time_series: &visible_time_series,
+                plot_window,
+            };
+            egui_dock::DockArea::new(&mut self.dock_state)
+                .show_inside(ui, &mut tab_viewer);
 
             ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
                 powered_by_egui_and_eframe(ui);
@@ -336,6 +684,7 @@ impl eframe::App for PatinaSystemMonitor {
 
     /// Called by the frame work to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.snapshot_dock_layout();
         eframe::set_value(storage, eframe::APP_KEY, self);
     }
 }
@@ -354,6 +703,120 @@ fn powered_by_egui_and_eframe(ui: &mut egui::Ui) {
     });
 }
 
+/// Appends `datagram` to `leftover` and, if the result contains at least one newline,
+/// returns the portion up to and including the last one, lossily decoded as UTF-8,
+/// leaving any trailing partial line in `leftover`. Returns `None` (with `datagram`
+/// still appended to `leftover`) when no line is complete yet. Operating on raw bytes
+/// rather than `&str` is what lets a datagram boundary split a line's multibyte UTF-8
+/// character without corrupting the carried-over remainder.
+fn reassemble_udp_lines(leftover: &mut Vec<u8>, datagram: &[u8]) -> Option<String> {
+    leftover.extend_from_slice(datagram);
+
+    let last_newline = leftover.iter().rposition(|&b| b == b'\n')?;
+    let remainder = leftover.split_off(last_newline + 1);
+    let complete = String::from_utf8_lossy(leftover).into_owned();
+    *leftover = remainder;
+    Some(complete)
+}
+
+/// Binds a TCP listener on `port` and spawns a thread that accepts connections,
+/// handing each one off to its own `handle_client` thread. Returns a sender whose
+/// disconnection (on drop) signals the accept loop to stop spawning new clients.
+fn spawn_tcp_listener(
+    port: u16,
+    metrics_tx: Sender<(SocketAddr, Vec<OwnedParsedLine>)>,
+    ctx: Context,
+) -> Sender<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .expect("Failed to bind TCP listener, unhandled error.");
+
+    println!("Listening for TCP on port {}...", port);
+
+    let (listen_thread_tx, listen_thread_rx) = mpsc::channel::<()>();
+
+    thread::spawn(move || {
+        while listen_thread_rx.try_recv() != Err(mpsc::TryRecvError::Disconnected) {
+            for stream in listener.incoming() {
+                if listen_thread_rx.try_recv() == Err(mpsc::TryRecvError::Disconnected) {
+                    break;
+                }
+                match stream {
+                    Ok(stream) => {
+                        println!("New connection: {}", stream.peer_addr().unwrap());
+                        let tx_clone = metrics_tx.clone();
+                        let ctx_clone = ctx.clone();
+                        thread::spawn(|| handle_client(stream, tx_clone, ctx_clone));
+                    }
+                    Err(e) => eprintln!("Connection failed: {}", e),
+                }
+            }
+        }
+        println!("Closing TCP listening port.");
+    });
+
+    listen_thread_tx
+}
+
+/// Binds a UDP socket on `port` and spawns a thread that reads datagrams, splits them
+/// into line protocol lines (carrying any partial line over to the next datagram), and
+/// feeds the same `metrics_tx` channel used by the TCP path. Returns a sender whose
+/// disconnection (on drop) signals the receive loop to stop.
+fn spawn_udp_listener(
+    port: u16,
+    metrics_tx: Sender<(SocketAddr, Vec<OwnedParsedLine>)>,
+    ctx: Context,
+) -> Sender<()> {
+    let socket = UdpSocket::bind(("127.0.0.1", port))
+        .expect("Failed to bind UDP socket, unhandled error.");
+
+    println!("Listening for UDP on port {}...", port);
+
+    let (listen_thread_tx, listen_thread_rx) = mpsc::channel::<()>();
+
+    thread::spawn(move || {
+        let mut buf = [0u8; UDP_RECV_BUFFER_SIZE];
+        // Keyed by sender, since a single port can hear from multiple independent
+        // agents: a partial line from one must never be prepended to another's
+        // datagram. Raw bytes (not `String`) because a datagram boundary can also
+        // split a line's multibyte UTF-8 mid-character; decoding only happens once
+        // we've reassembled a complete line.
+        let mut leftovers: HashMap<SocketAddr, Vec<u8>> = HashMap::new();
+
+        while listen_thread_rx.try_recv() != Err(mpsc::TryRecvError::Disconnected) {
+            match socket.recv_from(&mut buf) {
+                Ok((len, socket_addr)) => {
+                    // Pulled out (rather than borrowed in place) so an idle source's
+                    // entry doesn't linger in `leftovers` forever: it's only put back
+                    // below if there's an incomplete line to carry over.
+                    let mut leftover = leftovers.remove(&socket_addr).unwrap_or_default();
+                    let Some(complete) = reassemble_udp_lines(&mut leftover, &buf[..len]) else {
+                        leftovers.insert(socket_addr, leftover);
+                        continue;
+                    };
+                    if !leftover.is_empty() {
+                        leftovers.insert(socket_addr, leftover);
+                    }
+
+                    let lines: Vec<OwnedParsedLine> = parse_lines(&complete)
+                        .filter(|x| x.is_ok())
+                        .map(|x| OwnedParsedLine::from_parsed(x.as_ref().unwrap(), socket_addr))
+                        .collect();
+
+                    if !lines.is_empty() && metrics_tx.send((socket_addr, lines)).is_err() {
+                        eprintln!("Failed to send parsed data to main thread. Exiting UDP loop.");
+                        return;
+                    }
+                    ctx.request_repaint();
+                }
+                Err(e) => eprintln!("Error reading from UDP socket: {}", e),
+            }
+        }
+        println!("Closing UDP listening port.");
+    });
+
+    listen_thread_tx
+}
+
 /// Handles an individual client connection.
 fn handle_client(
     stream: TcpStream,
@@ -368,7 +831,7 @@ fn handle_client(
             Ok(ref data) => {
                 let lines: Vec<OwnedParsedLine> = parse_lines(data)
                     .filter(|x| x.is_ok())
-                    .map(|x| x.as_ref().unwrap().into())
+                    .map(|x| OwnedParsedLine::from_parsed(x.as_ref().unwrap(), socket_addr))
                     .collect();
 
                 if sender.send((socket_addr, lines)).is_err() {
@@ -384,3 +847,42 @@ fn handle_client(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reassemble_udp_lines_carries_partial_line_over() {
+        let mut leftover = Vec::new();
+        assert_eq!(reassemble_udp_lines(&mut leftover, b"cpu,host=a usage"), None);
+        assert_eq!(leftover, b"cpu,host=a usage");
+
+        let complete = reassemble_udp_lines(&mut leftover, b"_idle=1 0\ncpu,host=b ").unwrap();
+        assert_eq!(complete, "cpu,host=a usage_idle=1 0\n");
+        assert_eq!(leftover, b"cpu,host=b ");
+    }
+
+    #[test]
+    fn reassemble_udp_lines_handles_multiple_complete_lines() {
+        let mut leftover = Vec::new();
+        let complete = reassemble_udp_lines(&mut leftover, b"cpu a=1 0\ncpu a=2 1\n").unwrap();
+        assert_eq!(complete, "cpu a=1 0\ncpu a=2 1\n");
+        assert!(leftover.is_empty());
+    }
+
+    #[test]
+    fn reassemble_udp_lines_does_not_corrupt_a_multibyte_char_split_across_datagrams() {
+        // "µ" is 2 bytes in UTF-8 (0xC2 0xB5); split the datagram between them.
+        let mu = "µ".as_bytes();
+        assert_eq!(mu.len(), 2);
+
+        let mut leftover = Vec::new();
+        let first = [&b"cpu tag="[..], &mu[..1]].concat();
+        assert_eq!(reassemble_udp_lines(&mut leftover, &first), None);
+
+        let second = [&mu[1..], &b"s value=1 0\n"[..]].concat();
+        let complete = reassemble_udp_lines(&mut leftover, &second).unwrap();
+        assert_eq!(complete, "cpu tag=µs value=1 0\n");
+    }
+}
@@ -1,23 +1,357 @@
-use egui::Ui;
+use crate::app::{OwnedParsedLine, TimeSeries};
+use egui::{Color32, Ui};
+use egui_plot::{Legend, Line, Plot, PlotBounds, PlotPoints};
+use std::any::Any;
+use std::collections::BTreeSet;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-pub trait Card {
-    fn ui(&mut self, ui: &mut egui::Ui);
+/// A single panel in the dockable workspace.
+pub trait Card: Any {
+    /// Text shown on the card's dock tab.
+    fn title(&self) -> String;
+
+    /// Draws the card's contents, given read-only access to the shared metric history.
+    /// `plot_window` is `Some(window)` while showing the live rolling `window`, which
+    /// pins the X axis to `[-window, 0]`; it's `None` for a historical `TimeRange`, which
+    /// keeps the X axis where the previous frame or the user's panning/zooming left it.
+    /// The X axis is not fitted to the data.
+    fn ui(
+        &mut self,
+        ui: &mut Ui,
+        time_series: &TimeSeries<OwnedParsedLine>,
+        plot_window: Option<Duration>,
+    );
+
+    /// Lets callers recover the concrete card type, e.g. to snapshot it for persistence.
+    fn as_any(&self) -> &dyn Any;
+}
+
+/// How a plotted field's raw value is mapped to the Y axis.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum FieldTransform {
+    Identity,
+    OneHundredMinusX,
+}
+
+impl FieldTransform {
+    fn apply(self, value: f64) -> f64 {
+        match self {
+            FieldTransform::Identity => value,
+            FieldTransform::OneHundredMinusX => 100.0 - value,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            FieldTransform::Identity => "Identity",
+            FieldTransform::OneHundredMinusX => "100 - x",
+        }
+    }
 }
 
+/// What a [`GraphCard`] plots: a measurement, the tags that must match, the fields to
+/// draw as separate lines, and how those field values are transformed and scaled.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct GraphQuery {
+    pub measurement: String,
+    pub tag_filters: Vec<(String, String)>,
+    pub fields: Vec<String>,
+    pub transform: FieldTransform,
+    pub y_min: f64,
+    pub y_max: f64,
+}
+
+impl Default for GraphQuery {
+    fn default() -> Self {
+        // Mirrors the monitor's original hardcoded total-CPU-usage graph.
+        GraphQuery {
+            measurement: "cpu".to_string(),
+            tag_filters: vec![("cpu".to_string(), "cpu-total".to_string())],
+            fields: vec!["usage_idle".to_string()],
+            transform: FieldTransform::OneHundredMinusX,
+            y_min: -1.1,
+            y_max: 100.0,
+        }
+    }
+}
+
+const LINE_COLORS: [Color32; 6] = [
+    Color32::RED,
+    Color32::BLUE,
+    Color32::GREEN,
+    Color32::GOLD,
+    Color32::from_rgb(255, 140, 0),
+    Color32::from_rgb(148, 0, 211),
+];
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct GraphCard {
     pub title: String,
+    pub query: GraphQuery,
 }
 
 impl Default for GraphCard {
     fn default() -> GraphCard {
         GraphCard {
-            title: "New Graph Card".to_string(),
+            title: "New Graph".to_string(),
+            query: GraphQuery::default(),
         }
     }
 }
 
+impl GraphCard {
+    fn matches_tag_filters(&self, line: &OwnedParsedLine) -> bool {
+        self.query
+            .tag_filters
+            .iter()
+            .all(|(key, value)| line.tags.iter().any(|(k, v)| k == key && v == value))
+    }
+
+    fn editor_ui(&mut self, ui: &mut Ui, time_series: &TimeSeries<OwnedParsedLine>) {
+        egui::CollapsingHeader::new("Query")
+            .id_salt("query_editor")
+            .show(ui, |ui| {
+                let measurements = distinct_measurements(time_series);
+                ui.horizontal(|ui| {
+                    ui.label("Measurement:");
+                    egui::ComboBox::from_id_salt("measurement")
+                        .selected_text(self.query.measurement.clone())
+                        .show_ui(ui, |ui| {
+                            for measurement in &measurements {
+                                ui.selectable_value(
+                                    &mut self.query.measurement,
+                                    measurement.clone(),
+                                    measurement,
+                                );
+                            }
+                        });
+                });
+
+                let tag_keys = distinct_tag_keys(time_series, &self.query.measurement);
+                ui.label("Tag filters:");
+                let mut remove_filter = None;
+                for (i, (key, value)) in self.query.tag_filters.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        egui::ComboBox::from_id_salt(("tag_key", i))
+                            .selected_text(key.clone())
+                            .show_ui(ui, |ui| {
+                                for tag_key in &tag_keys {
+                                    ui.selectable_value(key, tag_key.clone(), tag_key);
+                                }
+                            });
+                        let tag_values =
+                            distinct_tag_values(time_series, &self.query.measurement, key);
+                        egui::ComboBox::from_id_salt(("tag_value", i))
+                            .selected_text(value.clone())
+                            .show_ui(ui, |ui| {
+                                for tag_value in &tag_values {
+                                    ui.selectable_value(value, tag_value.clone(), tag_value);
+                                }
+                            });
+                        if ui.small_button("x").clicked() {
+                            remove_filter = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = remove_filter {
+                    self.query.tag_filters.remove(i);
+                }
+                if ui.button("Add tag filter").clicked() {
+                    self.query.tag_filters.push((String::new(), String::new()));
+                }
+
+                let fields = distinct_fields(time_series, &self.query.measurement);
+                ui.label("Fields:");
+                let mut remove_field = None;
+                for (i, field) in self.query.fields.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        egui::ComboBox::from_id_salt(("field", i))
+                            .selected_text(field.clone())
+                            .show_ui(ui, |ui| {
+                                for candidate in &fields {
+                                    ui.selectable_value(field, candidate.clone(), candidate);
+                                }
+                            });
+                        if ui.small_button("x").clicked() {
+                            remove_field = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = remove_field {
+                    self.query.fields.remove(i);
+                }
+                if ui.button("Add field").clicked() {
+                    self.query.fields.push(String::new());
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("Transform:");
+                    egui::ComboBox::from_id_salt("transform")
+                        .selected_text(self.query.transform.label())
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(
+                                &mut self.query.transform,
+                                FieldTransform::Identity,
+                                FieldTransform::Identity.label(),
+                            );
+                            ui.selectable_value(
+                                &mut self.query.transform,
+                                FieldTransform::OneHundredMinusX,
+                                FieldTransform::OneHundredMinusX.label(),
+                            );
+                        });
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Y range:");
+                    ui.add(egui::DragValue::new(&mut self.query.y_min).prefix("min: "));
+                    ui.add(egui::DragValue::new(&mut self.query.y_max).prefix("max: "));
+                });
+            });
+    }
+
+    fn plot_ui(
+        &self,
+        ui: &mut Ui,
+        time_series: &TimeSeries<OwnedParsedLine>,
+        plot_window: Option<Duration>,
+    ) {
+        let now_unix = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        let plot = Plot::new("graph").height(300.0).legend(Legend::default());
+
+        plot.show(ui, |plot_ui| {
+            for (i, field) in self.query.fields.iter().enumerate() {
+                let points: Vec<[f64; 2]> = time_series
+                    .data
+                    .iter()
+                    .filter(|d| d.data.measurement == self.query.measurement)
+                    .filter(|d| self.matches_tag_filters(&d.data))
+                    .map(|d| {
+                        [
+                            d.data.offset_timestamp_secs_f64(now_unix),
+                            self.query
+                                .transform
+                                .apply(d.data.get_field_as_f64(field, 0.0)),
+                        ]
+                    })
+                    .collect();
+
+                plot_ui.line(
+                    Line::new(PlotPoints::new(points))
+                        .name(field)
+                        .color(LINE_COLORS[i % LINE_COLORS.len()]),
+                );
+            }
+
+            // The configured Y range always applies. The X range is pinned to the live
+            // rolling window when `plot_window` is `Some`; otherwise (a historical
+            // `TimeRange`) X is left at whatever the previous frame or the user's
+            // pan/zoom settled on, so history isn't clamped to a fixed window.
+            let x_range = match plot_window {
+                Some(window) => [-window.as_secs_f64(), 0.0],
+                None => {
+                    let bounds = plot_ui.plot_bounds();
+                    [bounds.min()[0], bounds.max()[0]]
+                }
+            };
+            plot_ui.set_plot_bounds(PlotBounds::from_min_max(
+                [x_range[0], self.query.y_min],
+                [x_range[1], self.query.y_max],
+            ));
+        });
+    }
+}
+
 impl Card for GraphCard {
-    fn ui(&mut self, ui: &mut Ui) {
-        ui.heading(&self.title);
+    fn title(&self) -> String {
+        self.title.clone()
+    }
+
+    fn ui(
+        &mut self,
+        ui: &mut Ui,
+        time_series: &TimeSeries<OwnedParsedLine>,
+        plot_window: Option<Duration>,
+    ) {
+        // Scope every widget ID to this card instance so identically configured
+        // cards (e.g. two fresh "New Graph" tabs) don't collide when shown at once.
+        ui.push_id(self as *const GraphCard as usize, |ui| {
+            self.editor_ui(ui, time_series);
+            self.plot_ui(ui, time_series, plot_window);
+        });
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+fn distinct_measurements(time_series: &TimeSeries<OwnedParsedLine>) -> Vec<String> {
+    let set: BTreeSet<&str> = time_series
+        .data
+        .iter()
+        .map(|d| d.data.measurement.as_str())
+        .collect();
+    set.into_iter().map(str::to_string).collect()
+}
+
+fn distinct_tag_keys(time_series: &TimeSeries<OwnedParsedLine>, measurement: &str) -> Vec<String> {
+    let set: BTreeSet<&str> = time_series
+        .data
+        .iter()
+        .filter(|d| d.data.measurement == measurement)
+        .flat_map(|d| d.data.tags.iter().map(|(k, _)| k.as_str()))
+        .collect();
+    set.into_iter().map(str::to_string).collect()
+}
+
+fn distinct_tag_values(
+    time_series: &TimeSeries<OwnedParsedLine>,
+    measurement: &str,
+    tag_key: &str,
+) -> Vec<String> {
+    let set: BTreeSet<&str> = time_series
+        .data
+        .iter()
+        .filter(|d| d.data.measurement == measurement)
+        .flat_map(|d| d.data.tags.iter())
+        .filter(|(k, _)| k == tag_key)
+        .map(|(_, v)| v.as_str())
+        .collect();
+    set.into_iter().map(str::to_string).collect()
+}
+
+fn distinct_fields(time_series: &TimeSeries<OwnedParsedLine>, measurement: &str) -> Vec<String> {
+    let set: BTreeSet<&str> = time_series
+        .data
+        .iter()
+        .filter(|d| d.data.measurement == measurement)
+        .flat_map(|d| d.data.fields.iter().map(|(k, _)| k.as_str()))
+        .collect();
+    set.into_iter().map(str::to_string).collect()
+}
+
+/// Bridges egui_dock's generic tab callbacks to our [`Card`] trait objects.
+pub struct CardTabViewer<'a> {
+    pub time_series: &'a TimeSeries<OwnedParsedLine>,
+
+    /// Forwarded to [`Card::ui`] as `plot_window`: `Some(window)` while live, `None`
+    /// for a historical `TimeRange` so cards keep the user's X range instead of
+    /// clamping to 60s.
+    pub plot_window: Option<Duration>,
+}
+
+impl egui_dock::TabViewer for CardTabViewer<'_> {
+    type Tab = Box<dyn Card>;
+
+    fn title(&mut self, tab: &mut Self::Tab) -> egui::WidgetText {
+        tab.title().into()
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, tab: &mut Self::Tab) {
+        tab.ui(ui, self.time_series, self.plot_window);
     }
 }
@@ -1,8 +1,10 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
+mod alerts;
 mod app;
 mod card;
+mod store;
 
-pub use app::PatinaSystemMonitor;
+pub use app::{ListenConfig, PatinaSystemMonitor, Transport};
 pub use card::Card;
 pub use card::GraphCard;
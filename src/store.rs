@@ -0,0 +1,311 @@
+use crate::app::{MeasurementValue, OwnedParsedLine, TimeSeries};
+use rusqlite::{params, Connection};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+/// Stand-in for rows written before the `source` column existed, or whose stored
+/// address failed to parse.
+fn unknown_source() -> SocketAddr {
+    SocketAddr::from(([0, 0, 0, 0], 0))
+}
+
+/// One (measurement, tag set, field) sample as read back out of the store.
+#[derive(Debug, Clone)]
+pub(crate) struct MetricRow {
+    pub(crate) measurement: String,
+    pub(crate) tags: Vec<(String, String)>,
+    pub(crate) field: String,
+    pub(crate) unix_timestamp: Duration,
+    pub(crate) value: f64,
+    pub(crate) source: SocketAddr,
+}
+
+struct HistoryQuery {
+    range_start: Duration,
+    range_end: Duration,
+    reply_tx: Sender<Vec<MetricRow>>,
+}
+
+enum StoreCommand {
+    Write(Vec<OwnedParsedLine>),
+    Query(HistoryQuery),
+}
+
+/// A handle to the background writer/reader thread backing the on-disk metric store.
+/// Cloning is cheap: it's just another sender onto the same command channel, so the
+/// writer thread (and its single SQLite connection) is shared.
+#[derive(Clone)]
+pub(crate) struct MetricStoreHandle {
+    command_tx: Sender<StoreCommand>,
+}
+
+impl MetricStoreHandle {
+    /// Opens (or creates) the SQLite database at `path` and spawns the thread that owns
+    /// the connection for the lifetime of the app.
+    pub(crate) fn open(path: &Path) -> rusqlite::Result<Self> {
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS metrics (
+                measurement TEXT NOT NULL,
+                tags        TEXT NOT NULL,
+                field       TEXT NOT NULL,
+                unix_nanos  INTEGER NOT NULL,
+                value       REAL NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS metrics_lookup
+                ON metrics (measurement, field, unix_nanos);",
+        )?;
+        // Added after the table above shipped; ignore the error on databases that
+        // already have the column.
+        let _ = conn.execute("ALTER TABLE metrics ADD COLUMN source TEXT NOT NULL DEFAULT ''", []);
+
+        let (command_tx, command_rx) = mpsc::channel::<StoreCommand>();
+        thread::spawn(move || run_store_thread(conn, command_rx));
+
+        Ok(MetricStoreHandle { command_tx })
+    }
+
+    /// Queues a batch of lines to be appended to the store. Never blocks the caller on
+    /// disk I/O: the write happens on the store's own thread.
+    pub(crate) fn write(&self, lines: Vec<OwnedParsedLine>) {
+        let _ = self.command_tx.send(StoreCommand::Write(lines));
+    }
+
+    /// Queues a query for every sample in `[range_start, range_end]` and returns a
+    /// receiver the caller can poll (e.g. with `try_recv`) without blocking.
+    pub(crate) fn query(&self, range_start: Duration, range_end: Duration) -> Receiver<Vec<MetricRow>> {
+        let (reply_tx, reply_rx) = mpsc::channel();
+        let _ = self.command_tx.send(StoreCommand::Query(HistoryQuery {
+            range_start,
+            range_end,
+            reply_tx,
+        }));
+        reply_rx
+    }
+}
+
+fn run_store_thread(mut conn: Connection, command_rx: Receiver<StoreCommand>) {
+    for command in command_rx {
+        match command {
+            StoreCommand::Write(lines) => {
+                if let Err(e) = write_lines(&mut conn, &lines) {
+                    eprintln!("Failed to write metrics to store: {}", e);
+                }
+            }
+            StoreCommand::Query(query) => {
+                let rows = read_range(&conn, query.range_start, query.range_end).unwrap_or_else(|e| {
+                    eprintln!("Failed to query metric store: {}", e);
+                    Vec::new()
+                });
+                let _ = query.reply_tx.send(rows);
+            }
+        }
+    }
+}
+
+/// Tags are stored as a single sorted `key=value,key2=value2` string so that rows for
+/// the same series can be grouped back together on read without a separate tags table.
+fn serialize_tags(tags: &[(String, String)]) -> String {
+    let mut sorted: Vec<&(String, String)> = tags.iter().collect();
+    sorted.sort_by(|a, b| a.0.cmp(&b.0));
+    sorted
+        .iter()
+        .map(|(k, v)| format!("{k}={v}"))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn deserialize_tags(serialized: &str) -> Vec<(String, String)> {
+    if serialized.is_empty() {
+        return Vec::new();
+    }
+    serialized
+        .split(',')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+/// Writes every field of every line in one transaction, so a batch from the channel
+/// becomes a single fsync'd commit instead of one per field — without this the store
+/// thread can't keep up with a high-frequency local feed and the channel backs up.
+fn write_lines(conn: &mut Connection, lines: &[OwnedParsedLine]) -> rusqlite::Result<()> {
+    let tx = conn.transaction()?;
+    {
+        let mut statement = tx.prepare(
+            "INSERT INTO metrics (measurement, tags, field, unix_nanos, value, source)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+
+        for line in lines {
+            let tags = serialize_tags(&line.tags);
+            let unix_nanos = line.unix_timestamp.as_nanos() as i64;
+            let source = line.source.to_string();
+
+            for (field, value) in &line.fields {
+                let value: f64 = value.into();
+                statement.execute(params![line.measurement, tags, field, unix_nanos, value, source])?;
+            }
+        }
+    }
+    tx.commit()
+}
+
+fn read_range(
+    conn: &Connection,
+    range_start: Duration,
+    range_end: Duration,
+) -> rusqlite::Result<Vec<MetricRow>> {
+    let mut statement = conn.prepare(
+        "SELECT measurement, tags, field, unix_nanos, value, source
+         FROM metrics
+         WHERE unix_nanos BETWEEN ?1 AND ?2
+         ORDER BY unix_nanos ASC, measurement ASC, tags ASC, source ASC",
+    )?;
+
+    let rows = statement.query_map(
+        params![range_start.as_nanos() as i64, range_end.as_nanos() as i64],
+        |row| {
+            let unix_nanos: i64 = row.get(3)?;
+            let source = row
+                .get::<_, String>(5)?
+                .parse()
+                .unwrap_or_else(|_| unknown_source());
+            Ok(MetricRow {
+                measurement: row.get(0)?,
+                tags: deserialize_tags(&row.get::<_, String>(1)?),
+                field: row.get(2)?,
+                unix_timestamp: Duration::from_nanos(unix_nanos as u64),
+                value: row.get(4)?,
+                source,
+            })
+        },
+    )?;
+
+    rows.collect()
+}
+
+/// Regroups flat `(measurement, tags, field, timestamp, value, source)` rows back into
+/// [`OwnedParsedLine`]s (one per distinct measurement/tags/timestamp/source) so the
+/// existing `Card` plotting code can treat historical data exactly like the live window.
+///
+/// Relies on `rows` being grouped by (measurement, tags, source) within each timestamp —
+/// `read_range`'s `ORDER BY` guarantees this — since it only ever compares a row against
+/// the line most recently started.
+pub(crate) fn rows_to_time_series(rows: Vec<MetricRow>) -> TimeSeries<OwnedParsedLine> {
+    let mut time_series = TimeSeries::new();
+    let mut current: Option<OwnedParsedLine> = None;
+
+    for row in rows {
+        let starts_new_line = match &current {
+            Some(line) => {
+                line.measurement != row.measurement
+                    || line.unix_timestamp != row.unix_timestamp
+                    || line.tags != row.tags
+                    || line.source != row.source
+            }
+            None => true,
+        };
+
+        if starts_new_line {
+            if let Some(line) = current.take() {
+                time_series.push(line.unix_timestamp, line);
+            }
+            current = Some(OwnedParsedLine {
+                measurement: row.measurement,
+                tags: row.tags,
+                fields: Vec::new(),
+                unix_timestamp: row.unix_timestamp,
+                source: row.source,
+            });
+        }
+
+        current
+            .as_mut()
+            .unwrap()
+            .fields
+            .push((row.field, MeasurementValue::F64(row.value)));
+    }
+
+    if let Some(line) = current {
+        time_series.push(line.unix_timestamp, line);
+    }
+
+    time_series
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serialize_tags_sorts_by_key() {
+        let tags = vec![("host".to_string(), "b".to_string()), ("cpu".to_string(), "cpu-total".to_string())];
+        assert_eq!(serialize_tags(&tags), "cpu=cpu-total,host=b");
+    }
+
+    #[test]
+    fn tags_round_trip_through_serialize_and_deserialize() {
+        let tags = vec![
+            ("cpu".to_string(), "cpu-total".to_string()),
+            ("host".to_string(), "my-host".to_string()),
+        ];
+        assert_eq!(deserialize_tags(&serialize_tags(&tags)), tags);
+        assert_eq!(deserialize_tags(&serialize_tags(&[])), Vec::<(String, String)>::new());
+    }
+
+    fn row(
+        measurement: &str,
+        tags: &[(&str, &str)],
+        field: &str,
+        unix_timestamp: Duration,
+        value: f64,
+        source: SocketAddr,
+    ) -> MetricRow {
+        MetricRow {
+            measurement: measurement.to_string(),
+            tags: tags
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            field: field.to_string(),
+            unix_timestamp,
+            value,
+            source,
+        }
+    }
+
+    /// Two distinct series (different tag sets) can share a timestamp; rows_to_time_series
+    /// must not merge their fields just because they're adjacent in timestamp order, as
+    /// long as `read_range`'s `ORDER BY` keeps each series' rows contiguous (see
+    /// `rows_to_time_series`'s doc comment).
+    #[test]
+    fn rows_to_time_series_keeps_series_with_same_timestamp_distinct() {
+        let t = Duration::from_secs(1);
+        let source = unknown_source();
+        let rows = vec![
+            row("cpu", &[("cpu", "cpu0")], "usage_idle", t, 90.0, source),
+            row("cpu", &[("cpu", "cpu0")], "usage_user", t, 10.0, source),
+            row("cpu", &[("cpu", "cpu1")], "usage_idle", t, 80.0, source),
+            row("cpu", &[("cpu", "cpu1")], "usage_user", t, 20.0, source),
+        ];
+
+        let time_series = rows_to_time_series(rows);
+        assert_eq!(time_series.data.len(), 2);
+
+        let cpu0 = &time_series.data[0].data;
+        assert_eq!(cpu0.tags, vec![("cpu".to_string(), "cpu0".to_string())]);
+        assert_eq!(cpu0.fields.len(), 2);
+
+        let cpu1 = &time_series.data[1].data;
+        assert_eq!(cpu1.tags, vec![("cpu".to_string(), "cpu1".to_string())]);
+        assert_eq!(cpu1.fields.len(), 2);
+    }
+}